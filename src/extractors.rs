@@ -0,0 +1,194 @@
+//! Per-site product extractors.
+//!
+//! Different storefronts expose product data in wildly different markup, so a
+//! single hardcoded set of selectors only ever works on one site. Instead each
+//! site is described by an [`Extractor`]; the [`Registry`] picks one per page by
+//! matching on the page's host, yt-dlp-style, and falls back to a generic
+//! schema.org reader when nothing site-specific matches.
+
+use scraper::{Html, Selector};
+use serde::Serialize;
+use url::Url;
+
+#[derive(Debug, Serialize)]
+pub struct Product {
+    pub url: String,
+    pub name: String,
+    pub price: String,
+}
+
+/// A strategy for pulling [`Product`]s out of one family of pages.
+///
+/// Extractors are shared across concurrent fetch tasks, so they must be
+/// `Send + Sync`.
+pub trait Extractor: Send + Sync {
+    /// Whether this extractor knows how to read `url`'s host.
+    fn matches(&self, url: &Url) -> bool;
+
+    /// Extract every product found in an already-parsed document.
+    fn extract(&self, doc: &Html, page_url: &Url) -> Vec<Product>;
+}
+
+/// Ordered set of extractors, consulted first-match-wins per page.
+pub struct Registry {
+    extractors: Vec<Box<dyn Extractor>>,
+}
+
+impl Registry {
+    /// Registry wired up with the built-in extractors. Site-specific
+    /// extractors come first; the generic schema.org reader is the fallback.
+    pub fn with_defaults() -> Self {
+        Registry {
+            extractors: vec![
+                Box::new(WalmartExtractor),
+                Box::new(SchemaOrgExtractor),
+            ],
+        }
+    }
+
+    /// Run the first extractor whose host matches `page_url`.
+    pub fn extract(&self, doc: &Html, page_url: &Url) -> Vec<Product> {
+        for extractor in &self.extractors {
+            if extractor.matches(page_url) {
+                return extractor.extract(doc, page_url);
+            }
+        }
+        Vec::new()
+    }
+}
+
+/// Walmart search/category pages.
+pub struct WalmartExtractor;
+
+impl Extractor for WalmartExtractor {
+    fn matches(&self, url: &Url) -> bool {
+        url.domain()
+            .map(|d| d.contains("walmart."))
+            .unwrap_or(false)
+    }
+
+    /// Best-effort extraction of Walmart product tiles.
+    /// NOTE: selectors may need adjustment if Walmart changes their HTML.
+    fn extract(&self, document: &Html, page_url: &Url) -> Vec<Product> {
+        // Each product tile – this is a best-effort selector.
+        // You can refine this by inspecting Walmart's HTML with browser dev tools.
+        let product_selector =
+            Selector::parse("div[data-item-id], div[data-automation-id='productTile']").unwrap();
+
+        // Name and price selectors (fallback to common patterns)
+        let name_selector = Selector::parse(
+            "[data-automation-id='product-title'], a[aria-label], div[data-automation-id='product-title-link']",
+        )
+        .unwrap();
+        let price_selector = Selector::parse(
+            "[data-automation-id='product-price'], span[aria-hidden='true'], div.price-main span",
+        )
+        .unwrap();
+
+        let link_selector = Selector::parse("a").unwrap();
+
+        let mut products = Vec::new();
+        for product in document.select(&product_selector) {
+            // Name
+            let name = product
+                .select(&name_selector)
+                .next()
+                .map(|e| e.text().collect::<String>())
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default();
+
+            // Price (this may include currency symbol)
+            let price = product
+                .select(&price_selector)
+                .next()
+                .map(|e| e.text().collect::<String>())
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default();
+
+            if name.is_empty() || price.is_empty() {
+                continue;
+            }
+
+            // Product URL (first link inside the tile)
+            let product_url = product
+                .select(&link_selector)
+                .next()
+                .and_then(|a| a.value().attr("href"))
+                .and_then(|href| page_url.join(href).ok())
+                .map(|u| u.to_string())
+                .unwrap_or_else(|| page_url.to_string());
+
+            products.push(Product {
+                url: product_url,
+                name,
+                price,
+            });
+        }
+
+        products
+    }
+}
+
+/// Generic reader for pages marked up with schema.org `itemprop` attributes.
+/// Matches any host, so it serves as the catch-all fallback.
+pub struct SchemaOrgExtractor;
+
+impl Extractor for SchemaOrgExtractor {
+    fn matches(&self, _url: &Url) -> bool {
+        true
+    }
+
+    fn extract(&self, document: &Html, page_url: &Url) -> Vec<Product> {
+        // Product scopes, per schema.org/Product.
+        let product_selector =
+            Selector::parse("[itemtype$='schema.org/Product'], [itemscope][itemtype*='Product']")
+                .unwrap();
+        let name_selector = Selector::parse("[itemprop='name']").unwrap();
+        let price_selector =
+            Selector::parse("[itemprop='price'], [itemprop='lowPrice']").unwrap();
+        let link_selector = Selector::parse("[itemprop='url'], a").unwrap();
+
+        let mut products = Vec::new();
+        for product in document.select(&product_selector) {
+            let name = product
+                .select(&name_selector)
+                .next()
+                .map(|e| e.text().collect::<String>())
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default();
+
+            // Price may live in the text or in a `content` attribute.
+            let price = product
+                .select(&price_selector)
+                .next()
+                .map(|e| {
+                    e.value()
+                        .attr("content")
+                        .map(|c| c.to_string())
+                        .unwrap_or_else(|| e.text().collect::<String>())
+                })
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default();
+
+            if name.is_empty() || price.is_empty() {
+                continue;
+            }
+
+            let product_url = product
+                .select(&link_selector)
+                .next()
+                .and_then(|a| a.value().attr("href").or_else(|| a.value().attr("content")))
+                .and_then(|href| page_url.join(href).ok())
+                .map(|u| u.to_string())
+                .unwrap_or_else(|| page_url.to_string());
+
+            products.push(Product {
+                url: product_url,
+                name,
+                price,
+            });
+        }
+
+        products
+    }
+}
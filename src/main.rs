@@ -1,199 +1,724 @@
+use clap::{Args, Parser, Subcommand};
+use rand::Rng;
 use reqwest::Client;
 use scraper::{Html, Selector};
-use std::collections::{HashSet, VecDeque};
-use std::env;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
-use std::fs::File;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use url::Url;
 
-use csv::Writer;
-use serde::Serialize;
+use tokio::sync::{mpsc, Mutex};
 
-#[derive(Debug, Serialize)]
-struct Product {
+mod extractors;
+mod robots;
+mod storage;
+use extractors::{Product, Registry};
+use robots::RobotsRules;
+use storage::Storage;
+
+/// Default number of fetches allowed in flight at once.
+const DEFAULT_CONCURRENCY: usize = 20;
+
+/// User-Agent sent on every request unless overridden with `--user-agent`.
+const DEFAULT_USER_AGENT: &str = "rust-mini-webcrawler";
+
+/// Per-host state guarding politeness: cached robots rules plus the time the
+/// next request to that host is allowed to go out.
+struct HostState {
+    rules: Arc<RobotsRules>,
+    /// Earliest time the next fetch to this host may go out: the most
+    /// recently reserved slot plus the crawl delay.
+    next_allowed: Option<Instant>,
+}
+
+type Hosts = Arc<Mutex<HashMap<String, HostState>>>;
+
+/// Retry tuning for transient fetch failures.
+const INITIAL_BACKOFF_MS: u64 = 300;
+const MAX_BACKOFF_MS: u64 = 30_000;
+const MAX_ATTEMPTS: u32 = 10;
+
+/// How many extra fetches to try when a page parses to zero products but
+/// looks like it was served truncated.
+const EMPTY_RETRY_CAP: u32 = 3;
+
+/// A body shorter than this many bytes is treated as an abruptly-cut response,
+/// not a real (if small) page; only near-empty responses trip this, so typical
+/// nav/category pages are left alone.
+const TRUNCATED_LEN: usize = 256;
+
+/// Why a fetch failed, after all retries were exhausted.
+enum FetchError {
+    /// Server told us the page is gone; never retried.
+    NotFound,
+    /// Transient error (timeout, 5xx, body read) that survived every retry.
+    Transient(String),
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::NotFound => write!(f, "404 Not Found"),
+            FetchError::Transient(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+/// Outcome of processing a single URL, reported back to the main loop so the
+/// crawl summary can tally succeeded vs. failed pages.
+enum Outcome {
+    Succeeded,
+    Failed(String),
+    /// Path was disallowed by the host's robots.txt.
+    Skipped,
+}
+
+/// A fully processed page handed back from a spawned task.
+struct Fetched {
+    url: Url,
+    outcome: Outcome,
+    products: Vec<Product>,
+    links: Vec<Url>,
+}
+
+/// A tiny multi-site product scraper.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Crawl a domain starting from a seed URL, following in-domain links.
+    Crawl(CrawlArgs),
+    /// Extract products from a file of URLs without any link discovery.
+    FetchList(FetchListArgs),
+    /// Print the stored price timeline for a product URL (sqlite backend).
+    History(HistoryArgs),
+}
+
+/// Knobs shared by the `crawl` and `fetch-list` fetching modes.
+#[derive(Args)]
+struct FetchOpts {
+    /// Number of fetches allowed in flight at once.
+    #[arg(long, default_value_t = DEFAULT_CONCURRENCY)]
+    concurrency: usize,
+    /// User-Agent header, also used to select the robots.txt group.
+    #[arg(long, default_value = DEFAULT_USER_AGENT)]
+    user_agent: String,
+    /// Politeness delay in ms between requests to a host when robots.txt
+    /// declares no Crawl-delay of its own.
+    #[arg(long, default_value_t = 0)]
+    delay: u64,
+    /// Output backend.
+    #[arg(long, default_value = "csv")]
+    output: String,
+}
+
+#[derive(Args)]
+struct CrawlArgs {
+    /// Seed URL to start crawling from.
+    start_url: String,
+    /// Maximum number of pages to fetch.
+    #[arg(default_value_t = 20)]
+    max_pages: usize,
+    #[command(flatten)]
+    fetch: FetchOpts,
+}
+
+#[derive(Args)]
+struct FetchListArgs {
+    /// File of newline-delimited URLs to extract products from.
+    file: String,
+    #[command(flatten)]
+    fetch: FetchOpts,
+}
+
+#[derive(Args)]
+struct HistoryArgs {
+    /// Product URL to look up.
     url: String,
-    name: String,
-    price: String,
 }
 
 #[tokio::main]
 async fn main() {
-    // CLI: cargo run -- <start_url> [max_pages]
-    let args: Vec<String> = env::args().collect();
+    let cli = Cli::parse();
 
-    if args.len() < 2 {
-        eprintln!("Usage: cargo run -- <start_url> [max_pages]");
-        return;
-    }
-
-    let start_url_str = &args[1];
-    let max_pages: usize = if args.len() >= 3 {
-        args[2].parse().unwrap_or(20)
-    } else {
-        20
+    let result = match cli.command {
+        Command::Crawl(args) => run_crawl(args).await,
+        Command::FetchList(args) => run_fetch_list(args).await,
+        Command::History(args) => print_history(&args.url),
     };
 
-    let start_url = match Url::parse(start_url_str) {
-        Ok(url) => url,
-        Err(e) => {
-            eprintln!("Invalid start URL: {e}");
-            return;
-        }
-    };
+    if let Err(e) = result {
+        eprintln!("Error: {e}");
+    }
+}
+
+async fn run_crawl(args: CrawlArgs) -> Result<(), Box<dyn Error>> {
+    let start_url = Url::parse(&args.start_url)?;
+    let default_delay = Duration::from_millis(args.fetch.delay);
 
     println!("Starting crawl at: {}", start_url);
-    println!("Max pages: {}", max_pages);
+    println!("Max pages: {}", args.max_pages);
+    println!("Concurrency: {}", args.fetch.concurrency);
+    println!("User-Agent: {}", args.fetch.user_agent);
+    println!("Output: {}", args.fetch.output);
+
+    let storage = storage::open(&args.fetch.output)?;
+
+    crawl_and_extract(
+        start_url,
+        args.max_pages,
+        args.fetch.concurrency,
+        args.fetch.user_agent,
+        default_delay,
+        storage,
+    )
+    .await
+}
+
+async fn run_fetch_list(args: FetchListArgs) -> Result<(), Box<dyn Error>> {
+    let contents = std::fs::read_to_string(&args.file)?;
+    let urls: Vec<Url> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| match Url::parse(line) {
+            Ok(url) => Some(url),
+            Err(e) => {
+                eprintln!("Skipping invalid URL '{line}': {e}");
+                None
+            }
+        })
+        .collect();
+
+    let default_delay = Duration::from_millis(args.fetch.delay);
+
+    println!("Fetching {} URL(s) from {}", urls.len(), args.file);
+    println!("Concurrency: {}", args.fetch.concurrency);
+    println!("User-Agent: {}", args.fetch.user_agent);
+    println!("Output: {}", args.fetch.output);
+
+    let storage = storage::open(&args.fetch.output)?;
+
+    fetch_list(
+        urls,
+        args.fetch.concurrency,
+        args.fetch.user_agent,
+        default_delay,
+        storage,
+    )
+    .await
+}
 
-    if let Err(e) = crawl_and_extract(start_url, max_pages).await {
-        eprintln!("Crawl failed: {e}");
+/// Print the latest price and full price timeline for `url` from the SQLite
+/// backend.
+#[cfg(feature = "sqlite")]
+fn print_history(url: &str) -> Result<(), Box<dyn Error>> {
+    let store = storage::SqliteStorage::open("products.db")?;
+    let timeline = store.history(url)?;
+
+    if timeline.is_empty() {
+        println!("No price history recorded for {url}");
+        return Ok(());
+    }
+
+    if let Some((fetched_at, price)) = timeline.last() {
+        println!("Latest price for {url}: {price} (as of {fetched_at})");
     }
+    println!("\nPrice history:");
+    for (fetched_at, price) in &timeline {
+        println!("  {fetched_at}  {price}");
+    }
+    Ok(())
 }
 
-async fn crawl_and_extract(start_url: Url, max_pages: usize) -> Result<(), Box<dyn Error>> {
-    let client = Client::new();
-    let link_selector = Selector::parse("a").unwrap();
+/// Without the `sqlite` feature there is no price history to query.
+#[cfg(not(feature = "sqlite"))]
+fn print_history(_url: &str) -> Result<(), Box<dyn Error>> {
+    Err("history requires the sqlite backend; rebuild with --features sqlite".into())
+}
+
+async fn crawl_and_extract(
+    start_url: Url,
+    max_pages: usize,
+    concurrency: usize,
+    user_agent: String,
+    default_delay: Duration,
+    mut storage: Box<dyn Storage>,
+) -> Result<(), Box<dyn Error>> {
+    let client = Client::builder().user_agent(&user_agent).build()?;
+    let registry = Arc::new(Registry::with_defaults());
+    let user_agent = Arc::new(user_agent);
+    let hosts: Hosts = Arc::new(Mutex::new(HashMap::new()));
 
     let mut queue: VecDeque<Url> = VecDeque::new();
     let mut visited: HashSet<String> = HashSet::new();
 
     let start_domain = start_url.domain().map(|d| d.to_string());
 
-    // CSV writer – will write to products.csv in project root
-    let file = File::create("products.csv")?;
-    let mut writer = Writer::from_writer(file);
+    // Per-URL outcome tally for the final summary.
+    let mut succeeded = 0usize;
+    let mut failed = 0usize;
+    let mut skipped = 0usize;
 
-    // CSV header
-    writer.write_record(&["url", "name", "price"])?;
+    // Completed fetches flow back to the main loop through this channel; the
+    // channel is bounded to the pool size so spawned tasks can't run ahead of
+    // what we are able to drain.
+    let (tx, mut rx) = mpsc::channel::<Fetched>(concurrency);
+    let mut in_flight: usize = 0;
 
     queue.push_back(start_url);
 
-    while let Some(url) = queue.pop_front() {
-        if visited.len() >= max_pages {
-            println!("\nReached max pages limit ({}) – stopping crawl.", max_pages);
+    // Keep going while there is either queued work or an outstanding fetch.
+    while queue.len() + in_flight > 0 {
+        // Top the pool back up to `concurrency` in-flight fetches.
+        while in_flight < concurrency && visited.len() < max_pages {
+            let url = match queue.pop_front() {
+                Some(url) => url,
+                None => break,
+            };
+
+            let url_str = url.as_str().to_string();
+            if visited.contains(&url_str) {
+                continue;
+            }
+
+            // Reserve the slot against `visited`/`max_pages` *before* spawning
+            // so concurrent tasks can't overshoot the page budget.
+            visited.insert(url_str.clone());
+            in_flight += 1;
+
+            println!("=== Fetching ({}/{}) ===", visited.len(), max_pages);
+            println!("{url_str}");
+
+            let client = client.clone();
+            let tx = tx.clone();
+            let registry = Arc::clone(&registry);
+            let hosts = Arc::clone(&hosts);
+            let user_agent = Arc::clone(&user_agent);
+            let start_domain = start_domain.clone();
+            tokio::spawn(async move {
+                let fetched = process_url(
+                    &client,
+                    &registry,
+                    &hosts,
+                    &user_agent,
+                    default_delay,
+                    url,
+                    start_domain.as_deref(),
+                )
+                .await;
+                // The receiver lives for the whole crawl, so a send error only
+                // happens on shutdown; nothing useful to do but drop the body.
+                let _ = tx.send(fetched).await;
+            });
+        }
+
+        if in_flight == 0 {
+            // Nothing outstanding and we can't enqueue more (budget reached or
+            // queue drained) – we're done.
             break;
         }
 
-        let url_str = url.as_str().to_string();
-        if visited.contains(&url_str) {
-            continue;
+        // Drain one completed fetch.
+        let Fetched {
+            url,
+            outcome,
+            products,
+            links,
+        } = match rx.recv().await {
+            Some(fetched) => fetched,
+            None => break,
+        };
+        in_flight -= 1;
+
+        match outcome {
+            Outcome::Succeeded => succeeded += 1,
+            Outcome::Skipped => {
+                skipped += 1;
+                println!("Skipped (robots.txt): {}", url);
+                continue;
+            }
+            Outcome::Failed(e) => {
+                failed += 1;
+                eprintln!("Request failed for {}: {}", url, e);
+                continue;
+            }
         }
 
-        println!("\n=== Fetching ({}/{}) ===", visited.len() + 1, max_pages);
-        println!("{url_str}");
+        for product in &products {
+            storage.write(product)?;
+        }
 
-        visited.insert(url_str.clone());
+        // Enqueue newly discovered in-domain links.
+        for next_url in links {
+            let next_str = next_url.as_str().to_string();
+            if !visited.contains(&next_str) {
+                queue.push_back(next_url);
+            }
+        }
+    }
 
-        let body = match client.get(url.clone()).send().await {
-            Ok(resp) => match resp.text().await {
-                Ok(text) => text,
-                Err(e) => {
-                    eprintln!("Failed to read body for {}: {}", url, e);
-                    continue;
-                }
-            },
-            Err(e) => {
+    storage.flush()?;
+    println!("\nCrawl complete. Total pages visited: {}", visited.len());
+    println!("Pages succeeded: {succeeded}, failed: {failed}, skipped: {skipped}");
+
+    Ok(())
+}
+
+/// Extract products from a fixed list of URLs, without following any links.
+/// Shares the same fetch pool, extractor dispatch and output backend as
+/// [`crawl_and_extract`].
+async fn fetch_list(
+    urls: Vec<Url>,
+    concurrency: usize,
+    user_agent: String,
+    default_delay: Duration,
+    mut storage: Box<dyn Storage>,
+) -> Result<(), Box<dyn Error>> {
+    let client = Client::builder().user_agent(&user_agent).build()?;
+    let registry = Arc::new(Registry::with_defaults());
+    let user_agent = Arc::new(user_agent);
+    let hosts: Hosts = Arc::new(Mutex::new(HashMap::new()));
+
+    let total = urls.len();
+    let mut pending = urls.into_iter();
+
+    let mut succeeded = 0usize;
+    let mut failed = 0usize;
+    let mut skipped = 0usize;
+
+    let (tx, mut rx) = mpsc::channel::<Fetched>(concurrency);
+    let mut in_flight: usize = 0;
+    let mut dispatched = 0usize;
+
+    loop {
+        // Top the pool back up to `concurrency` in-flight fetches.
+        while in_flight < concurrency {
+            let url = match pending.next() {
+                Some(url) => url,
+                None => break,
+            };
+            in_flight += 1;
+            dispatched += 1;
+
+            println!("=== Fetching ({}/{}) ===", dispatched, total);
+            println!("{url}");
+
+            let client = client.clone();
+            let tx = tx.clone();
+            let registry = Arc::clone(&registry);
+            let hosts = Arc::clone(&hosts);
+            let user_agent = Arc::clone(&user_agent);
+            tokio::spawn(async move {
+                // `start_domain: None` keeps every host in scope; link
+                // discovery is ignored below so no crawling happens.
+                let fetched =
+                    process_url(&client, &registry, &hosts, &user_agent, default_delay, url, None)
+                        .await;
+                let _ = tx.send(fetched).await;
+            });
+        }
+
+        if in_flight == 0 {
+            break;
+        }
+
+        let Fetched {
+            url,
+            outcome,
+            products,
+            links: _,
+        } = match rx.recv().await {
+            Some(fetched) => fetched,
+            None => break,
+        };
+        in_flight -= 1;
+
+        match outcome {
+            Outcome::Succeeded => succeeded += 1,
+            Outcome::Skipped => {
+                skipped += 1;
+                println!("Skipped (robots.txt): {}", url);
+                continue;
+            }
+            Outcome::Failed(e) => {
+                failed += 1;
                 eprintln!("Request failed for {}: {}", url, e);
                 continue;
             }
+        }
+
+        for product in &products {
+            storage.write(product)?;
+        }
+    }
+
+    storage.flush()?;
+    println!("\nDone. URLs succeeded: {succeeded}, failed: {failed}, skipped: {skipped}");
+
+    Ok(())
+}
+
+/// Fetch one URL (with retries), extract its products and discover in-domain
+/// links. Runs inside a spawned task so everything it needs is owned.
+#[allow(clippy::too_many_arguments)]
+async fn process_url(
+    client: &Client,
+    registry: &Registry,
+    hosts: &Hosts,
+    user_agent: &str,
+    default_delay: Duration,
+    url: Url,
+    start_domain: Option<&str>,
+) -> Fetched {
+    let mut products = Vec::new();
+    let mut links = Vec::new();
+    let mut empty_attempt = 0;
+
+    // Load (and cache) this host's robots.txt before touching any page.
+    let rules = host_rules(client, hosts, user_agent, &url).await;
+
+    if !rules.allows(url.path()) {
+        return Fetched {
+            url,
+            outcome: Outcome::Skipped,
+            products,
+            links,
         };
+    }
 
-        // Extract product data from this page
-        extract_products(&body, &url, &mut writer)?;
-
-        // Normal link discovery to keep crawling within same domain
-        let document = Html::parse_document(&body);
-
-        for element in document.select(&link_selector) {
-            if let Some(href) = element.value().attr("href") {
-                if let Ok(next_url) = url.join(href) {
-                    if let Some(ref domain) = start_domain {
-                        if next_url
-                            .domain()
-                            .map(|d| d != domain)
-                            .unwrap_or(true)
-                        {
-                            continue;
-                        }
+    // Throttle to robots.txt Crawl-delay, or the configured default.
+    throttle(hosts, &url, rules.crawl_delay().unwrap_or(default_delay)).await;
+
+    let outcome = loop {
+        match fetch_with_retry(client, &url).await {
+            Ok(body) => {
+                // Parse in a scope so the (non-`Send`) `Html` is dropped before
+                // any `.await` below — the task is `tokio::spawn`ed.
+                let retry = {
+                    let document = Html::parse_document(&body);
+                    products = registry.extract(&document, &url);
+
+                    // Some storefronts intermittently serve truncated HTML that
+                    // parses to nothing; re-fetch a few times before giving up.
+                    if products.is_empty()
+                        && looks_truncated(&body)
+                        && empty_attempt < EMPTY_RETRY_CAP
+                    {
+                        true
+                    } else {
+                        links = discover_links(&document, &url, start_domain, &rules);
+                        false
                     }
+                };
 
-                    let next_str = next_url.as_str().to_string();
-                    if !visited.contains(&next_str) {
-                        queue.push_back(next_url);
-                    }
+                if retry {
+                    empty_attempt += 1;
+                    // Re-throttle: a bare `continue` would skip the per-host
+                    // politeness delay and burst the site with re-fetches.
+                    throttle(hosts, &url, rules.crawl_delay().unwrap_or(default_delay)).await;
+                    continue;
                 }
+
+                break Outcome::Succeeded;
             }
+            Err(e) => break Outcome::Failed(e.to_string()),
         }
+    };
+
+    Fetched {
+        url,
+        outcome,
+        products,
+        links,
     }
+}
 
-    writer.flush()?;
-    println!("\nCrawl complete. Total pages visited: {}", visited.len());
-    println!("Saved extracted products to products.csv");
+/// Fetch and cache the robots.txt rules for `url`'s host, reusing a prior fetch
+/// when one is already cached.
+async fn host_rules(
+    client: &Client,
+    hosts: &Hosts,
+    user_agent: &str,
+    url: &Url,
+) -> Arc<RobotsRules> {
+    let host = url.host_str().unwrap_or_default().to_string();
+
+    if let Some(state) = hosts.lock().await.get(&host) {
+        return Arc::clone(&state.rules);
+    }
 
-    Ok(())
+    // Fetch outside the lock; a concurrent task racing us just re-fetches once.
+    let rules = Arc::new(fetch_robots(client, url, user_agent).await);
+
+    let mut guard = hosts.lock().await;
+    let state = guard.entry(host).or_insert_with(|| HostState {
+        rules: Arc::clone(&rules),
+        next_allowed: None,
+    });
+    Arc::clone(&state.rules)
 }
 
-/// Try to extract product name + price from a Walmart-like search result page.
-/// NOTE: selectors may need adjustment if Walmart changes their HTML.
-fn extract_products(
-    html: &str,
-    page_url: &Url,
-    writer: &mut Writer<File>,
-) -> Result<(), Box<dyn Error>> {
-    let document = Html::parse_document(html);
+/// Fetch `/robots.txt` for the host of `url`; a missing or failed fetch yields
+/// an allow-all ruleset.
+async fn fetch_robots(client: &Client, url: &Url, user_agent: &str) -> RobotsRules {
+    let robots_url = match url.join("/robots.txt") {
+        Ok(u) => u,
+        Err(_) => return RobotsRules::allow_all(),
+    };
 
-    // Each product tile – this is a best-effort selector.
-    // You can refine this by inspecting Walmart's HTML with browser dev tools.
-    let product_selector =
-        Selector::parse("div[data-item-id], div[data-automation-id='productTile']").unwrap();
+    match client.get(robots_url).send().await {
+        Ok(resp) if resp.status().is_success() => match resp.text().await {
+            Ok(text) => RobotsRules::parse(&text, user_agent),
+            Err(_) => RobotsRules::allow_all(),
+        },
+        _ => RobotsRules::allow_all(),
+    }
+}
 
-    // Name and price selectors (fallback to common patterns)
-    let name_selector = Selector::parse(
-        "[data-automation-id='product-title'], a[aria-label], div[data-automation-id='product-title-link']",
-    )
-    .unwrap();
-    let price_selector = Selector::parse(
-        "[data-automation-id='product-price'], span[aria-hidden='true'], div.price-main span",
-    )
-    .unwrap();
+/// Reserve the next fetch slot for `url`'s host and sleep until it is due,
+/// keeping at least `delay` between consecutive requests to the same host.
+async fn throttle(hosts: &Hosts, url: &Url, delay: Duration) {
+    if delay.is_zero() {
+        return;
+    }
+    let host = url.host_str().unwrap_or_default().to_string();
+
+    let wait = {
+        let mut guard = hosts.lock().await;
+        let state = guard.entry(host).or_insert_with(|| HostState {
+            rules: Arc::new(RobotsRules::allow_all()),
+            next_allowed: None,
+        });
+        let now = Instant::now();
+        let slot = match state.next_allowed {
+            Some(next) if next > now => next,
+            _ => now,
+        };
+        // Reserve this slot and push the next one out by the delay.
+        state.next_allowed = Some(slot + delay);
+        slot.saturating_duration_since(now)
+    };
 
-    let link_selector = Selector::parse("a").unwrap();
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
 
-    for product in document.select(&product_selector) {
-        // Name
-        let name = product
-            .select(&name_selector)
-            .next()
-            .map(|e| e.text().collect::<String>())
-            .map(|s| s.trim().to_string())
-            .unwrap_or_default();
-
-        // Price (this may include currency symbol)
-        let price = product
-            .select(&price_selector)
-            .next()
-            .map(|e| e.text().collect::<String>())
-            .map(|s| s.trim().to_string())
-            .unwrap_or_default();
-
-        if name.is_empty() || price.is_empty() {
-            continue;
+/// GET `url`, retrying transient failures with exponential backoff + jitter.
+/// A 404 is non-retryable; 5xx, timeouts and body-read errors are retried, for
+/// at most [`MAX_ATTEMPTS`] attempts in total.
+async fn fetch_with_retry(client: &Client, url: &Url) -> Result<String, FetchError> {
+    let mut last_err = String::from("no attempts made");
+
+    for attempt in 0..MAX_ATTEMPTS {
+        // How long to wait before the next attempt; on a 429, a server-supplied
+        // `Retry-After` overrides the exponential backoff when present.
+        let mut delay = backoff_delay(attempt);
+
+        match client.get(url.clone()).send().await {
+            Ok(resp) => {
+                let status = resp.status();
+                if status == reqwest::StatusCode::NOT_FOUND {
+                    return Err(FetchError::NotFound);
+                }
+                if status.is_success() {
+                    match resp.text().await {
+                        Ok(text) => return Ok(text),
+                        Err(e) => last_err = e.to_string(),
+                    }
+                } else if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    // Honor `Retry-After` (delta-seconds) in place of the
+                    // usual backoff when the server sent one.
+                    if let Some(wait) = retry_after(&resp) {
+                        delay = wait;
+                    }
+                    last_err = format!("rate limited {status}");
+                } else {
+                    // 5xx and any other non-success (403, 3xx redirect loops,
+                    // etc.): don't scrape the error page — retry.
+                    last_err = format!("http error {status}");
+                }
+            }
+            Err(e) => last_err = e.to_string(),
         }
 
-        // Product URL (first link inside the tile)
-        let product_url = product
-            .select(&link_selector)
-            .next()
-            .and_then(|a| a.value().attr("href"))
-            .and_then(|href| page_url.join(href).ok())
-            .map(|u| u.to_string())
-            .unwrap_or_else(|| page_url.to_string());
-
-        writer.serialize(Product {
-            url: product_url,
-            name,
-            price,
-        })?;
+        // Back off before the next attempt (none after the last one).
+        if attempt + 1 < MAX_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+        }
     }
 
-    Ok(())
+    Err(FetchError::Transient(last_err))
+}
+
+/// The `Retry-After` header as a delay, when the server sent one as an integer
+/// number of seconds. HTTP-date forms are ignored in favor of plain backoff.
+fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    resp.headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Exponential backoff for `attempt` (0-based) with randomized jitter.
+fn backoff_delay(attempt: u32) -> Duration {
+    let base = INITIAL_BACKOFF_MS
+        .saturating_mul(1u64 << attempt.min(16))
+        .min(MAX_BACKOFF_MS);
+    let jitter = rand::thread_rng().gen_range(0..=INITIAL_BACKOFF_MS);
+    Duration::from_millis(base + jitter)
+}
+
+/// Heuristic: a page that clearly opened an `<html>`/`<body>` document but was
+/// cut off before closing it was probably served truncated, as is any body
+/// under [`TRUNCATED_LEN`] bytes. A small nav/category page above that size
+/// that closes its tags is *not* flagged, so we don't hammer polite sites with
+/// extra fetches just because they extracted nothing.
+fn looks_truncated(body: &str) -> bool {
+    let lower = body.to_lowercase();
+    let opened = lower.contains("<html") || lower.contains("<body");
+    let closed = lower.contains("</html>") || lower.contains("</body>");
+    // Opened a document but never closed it, or an abruptly tiny response.
+    (opened && !closed) || body.len() < TRUNCATED_LEN
+}
+
+/// All in-domain links reachable via `<a href>` from a parsed page, minus
+/// those whose path robots.txt disallows, used to keep crawling within the
+/// starting domain.
+fn discover_links(
+    document: &Html,
+    page_url: &Url,
+    start_domain: Option<&str>,
+    rules: &RobotsRules,
+) -> Vec<Url> {
+    let link_selector = Selector::parse("a").unwrap();
+    let mut links = Vec::new();
+    for element in document.select(&link_selector) {
+        if let Some(href) = element.value().attr("href") {
+            if let Ok(next_url) = page_url.join(href) {
+                if let Some(domain) = start_domain {
+                    if next_url.domain().map(|d| d != domain).unwrap_or(true) {
+                        continue;
+                    }
+                }
+                // Don't enqueue paths the host disallows.
+                if !rules.allows(next_url.path()) {
+                    continue;
+                }
+                links.push(next_url);
+            }
+        }
+    }
+    links
 }
@@ -0,0 +1,166 @@
+//! Minimal `robots.txt` parsing and politeness rules.
+//!
+//! Only the directives the crawler actually honors are modelled: `Disallow`
+//! path prefixes and `Crawl-delay`, both scoped to the configured user-agent
+//! (falling back to the `*` group).
+
+use std::time::Duration;
+
+/// The `robots.txt` rules that apply to one user-agent.
+pub struct RobotsRules {
+    disallow: Vec<String>,
+    crawl_delay: Option<Duration>,
+}
+
+impl RobotsRules {
+    /// An empty ruleset that allows every path — used when a site has no
+    /// `robots.txt` or it could not be fetched.
+    pub fn allow_all() -> Self {
+        RobotsRules {
+            disallow: Vec::new(),
+            crawl_delay: None,
+        }
+    }
+
+    /// Parse `robots.txt`, keeping the group that best matches `user_agent`.
+    /// A group naming our agent wins over the `*` group; if neither is present
+    /// everything is allowed.
+    pub fn parse(txt: &str, user_agent: &str) -> Self {
+        let ua = user_agent.to_lowercase();
+
+        // Accumulated per-group state while scanning records.
+        let mut agents: Vec<String> = Vec::new();
+        let mut disallow: Vec<String> = Vec::new();
+        let mut crawl_delay: Option<Duration> = None;
+        let mut seen_rule = false;
+
+        let mut star: Option<RobotsRules> = None;
+        let mut specific: Option<RobotsRules> = None;
+
+        // Commit the group being built into `star`/`specific` as appropriate.
+        let flush = |agents: &[String],
+                         disallow: &[String],
+                         crawl_delay: Option<Duration>,
+                         star: &mut Option<RobotsRules>,
+                         specific: &mut Option<RobotsRules>| {
+            for agent in agents {
+                let rules = RobotsRules {
+                    disallow: disallow.to_vec(),
+                    crawl_delay,
+                };
+                if agent == "*" {
+                    *star = Some(rules);
+                } else if !agent.is_empty() && ua.contains(agent.as_str()) {
+                    // A blank agent token (malformed `User-agent:`) would match
+                    // everything via `str::contains("")`, so ignore it.
+                    *specific = Some(rules);
+                }
+            }
+        };
+
+        for line in txt.lines() {
+            // Strip comments and surrounding whitespace.
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    // A User-agent line after rules starts a fresh record.
+                    if seen_rule {
+                        flush(&agents, &disallow, crawl_delay, &mut star, &mut specific);
+                        agents.clear();
+                        disallow.clear();
+                        crawl_delay = None;
+                        seen_rule = false;
+                    }
+                    agents.push(value.to_lowercase());
+                }
+                "disallow" => {
+                    seen_rule = true;
+                    if !value.is_empty() {
+                        disallow.push(value.to_string());
+                    }
+                }
+                "crawl-delay" => {
+                    seen_rule = true;
+                    // `Duration::from_secs_f64` panics on negative, NaN or
+                    // infinite input, all of which parse fine as `f64`; ignore
+                    // a malformed `Crawl-delay` rather than crash the crawl.
+                    if let Ok(secs) = value.parse::<f64>() {
+                        if secs.is_finite() && secs >= 0.0 {
+                            crawl_delay = Some(Duration::from_secs_f64(secs));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        flush(&agents, &disallow, crawl_delay, &mut star, &mut specific);
+
+        specific.or(star).unwrap_or_else(RobotsRules::allow_all)
+    }
+
+    /// Whether `path` is allowed by the `Disallow` rules.
+    pub fn allows(&self, path: &str) -> bool {
+        !self
+            .disallow
+            .iter()
+            .any(|prefix| path.starts_with(prefix.as_str()))
+    }
+
+    /// The `Crawl-delay` directive, if the site declared one.
+    pub fn crawl_delay(&self) -> Option<Duration> {
+        self.crawl_delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disallow_prefixes_are_honored() {
+        let rules = RobotsRules::parse("User-agent: *\nDisallow: /cart\n", "crawler");
+        assert!(!rules.allows("/cart"));
+        assert!(!rules.allows("/cart/items"));
+        assert!(rules.allows("/products"));
+    }
+
+    #[test]
+    fn specific_group_wins_over_star() {
+        let txt = "User-agent: *\nDisallow: /\n\nUser-agent: crawler\nDisallow: /private\n";
+        let rules = RobotsRules::parse(txt, "crawler");
+        assert!(rules.allows("/products"));
+        assert!(!rules.allows("/private"));
+    }
+
+    #[test]
+    fn empty_agent_token_does_not_match() {
+        // A blank `User-agent:` with `Disallow: /` must not block everything.
+        let rules = RobotsRules::parse("User-agent:\nDisallow: /\n", "crawler");
+        assert!(rules.allows("/anything"));
+    }
+
+    #[test]
+    fn crawl_delay_parsed() {
+        let rules = RobotsRules::parse("User-agent: *\nCrawl-delay: 2.5\n", "crawler");
+        assert_eq!(rules.crawl_delay(), Some(Duration::from_secs_f64(2.5)));
+    }
+
+    #[test]
+    fn malformed_crawl_delay_is_ignored_not_panicking() {
+        for bad in ["-1", "nan", "inf", "oops"] {
+            let txt = format!("User-agent: *\nCrawl-delay: {bad}\n");
+            let rules = RobotsRules::parse(&txt, "crawler");
+            assert_eq!(rules.crawl_delay(), None, "delay {bad:?} should be ignored");
+        }
+    }
+}
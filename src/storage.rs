@@ -0,0 +1,117 @@
+//! Output backends for extracted products.
+//!
+//! The CSV writer is the default and is always available; the SQLite backend
+//! (behind the `sqlite` feature) keeps a timestamped `price_history` table so
+//! repeated crawls accumulate a price timeline per product URL.
+
+use std::error::Error;
+use std::fs::File;
+
+use csv::Writer;
+
+use crate::extractors::Product;
+
+/// A sink for extracted products. Implementations decide how rows are
+/// persisted (a CSV file, a SQLite table, …).
+pub trait Storage {
+    /// Persist one extracted product.
+    fn write(&mut self, product: &Product) -> Result<(), Box<dyn Error>>;
+
+    /// Flush any buffered rows to the underlying store.
+    fn flush(&mut self) -> Result<(), Box<dyn Error>>;
+}
+
+/// Open the backend named by `kind` (`"csv"` or `"sqlite"`).
+pub fn open(kind: &str) -> Result<Box<dyn Storage>, Box<dyn Error>> {
+    match kind {
+        "csv" => Ok(Box::new(CsvStorage::create("products.csv")?)),
+        #[cfg(feature = "sqlite")]
+        "sqlite" => Ok(Box::new(SqliteStorage::open("products.db")?)),
+        #[cfg(not(feature = "sqlite"))]
+        "sqlite" => Err("sqlite backend not compiled in; rebuild with --features sqlite".into()),
+        other => Err(format!("unknown output backend '{other}' (expected csv|sqlite)").into()),
+    }
+}
+
+/// CSV backend — writes a flat `url,name,price` file, overwritten each crawl.
+pub struct CsvStorage {
+    writer: Writer<File>,
+}
+
+impl CsvStorage {
+    /// Create `path`, truncating any previous file, and write the header row.
+    pub fn create(path: &str) -> Result<Self, Box<dyn Error>> {
+        let file = File::create(path)?;
+        let mut writer = Writer::from_writer(file);
+        writer.write_record(["url", "name", "price"])?;
+        Ok(CsvStorage { writer })
+    }
+}
+
+impl Storage for CsvStorage {
+    fn write(&mut self, product: &Product) -> Result<(), Box<dyn Error>> {
+        self.writer.serialize(product)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// SQLite backend — appends every product as a timestamped row so prices can
+/// be tracked over time.
+#[cfg(feature = "sqlite")]
+pub struct SqliteStorage {
+    conn: rusqlite::Connection,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStorage {
+    /// Open (creating if needed) the database at `path` and ensure the
+    /// `price_history` table exists.
+    pub fn open(path: &str) -> Result<Self, Box<dyn Error>> {
+        let conn = rusqlite::Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS price_history (
+                 id         INTEGER PRIMARY KEY,
+                 url        TEXT NOT NULL,
+                 name       TEXT NOT NULL,
+                 price      TEXT NOT NULL,
+                 fetched_at TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_price_history_url ON price_history(url);",
+        )?;
+        Ok(SqliteStorage { conn })
+    }
+
+    /// The full price timeline for `url`, oldest first, as
+    /// `(fetched_at, price)` pairs.
+    pub fn history(&self, url: &str) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT fetched_at, price FROM price_history
+             WHERE url = ?1 ORDER BY fetched_at ASC, id ASC",
+        )?;
+        let rows = stmt
+            .query_map([url], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Storage for SqliteStorage {
+    fn write(&mut self, product: &Product) -> Result<(), Box<dyn Error>> {
+        self.conn.execute(
+            "INSERT INTO price_history (url, name, price, fetched_at)
+             VALUES (?1, ?2, ?3, datetime('now'))",
+            rusqlite::params![product.url, product.name, product.price],
+        )?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}